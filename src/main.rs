@@ -1,72 +1,268 @@
 #[macro_use]
 extern crate glium;
 extern crate image;
+extern crate fontdue;
+#[macro_use]
+extern crate bitflags;
 
-use glium::{Display, IndexBuffer, Program, Surface, VertexBuffer};
-use glium::draw_parameters::{BackfaceCullingMode, DrawParameters};
-use glium::glutin::{ContextBuilder, Event, EventsLoop, WindowBuilder, WindowEvent};
+use glium::{Display, Program, Surface, VertexBuffer};
+use glium::draw_parameters::{BackfaceCullingMode, DrawParameters, PolygonMode};
+use glium::glutin::{ContextBuilder, ElementState, Event, EventsLoop, KeyboardInput, MouseButton,
+                    MouseScrollDelta, VirtualKeyCode, WindowBuilder, WindowEvent};
 use glium::index::PrimitiveType;
-use glium::texture::{Texture2d, RawImage2d};
+use glium::texture::{ClientFormat, MipmapsOption, RawImage2d, Texture2d, UncompressedFloatFormat};
 use glium::uniforms::SamplerWrapFunction;
+use glium::Rect;
 
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 use std::f32::consts::{PI, FRAC_PI_2};
 use std::io::Cursor;
+use std::ops::{Add, Mul, Neg, Range, Sub};
 use std::time::{Instant};
 
-fn scale(a: &[f32; 3], scale: f32) -> [f32; 3] {
-    [ a[0] * scale, a[1] * scale, a[2] * scale ]
+/// A point or displacement in 3-space. Replaces the old free functions
+/// (`scale`, `add`, `subtract`, ...) that operated on `&[f32; 3]` with
+/// operator overloads and inherent methods, and adds a real cross product.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct Vec3 {
+    x: f32,
+    y: f32,
+    z: f32
 }
 
-fn negate(a: &[f32; 3]) -> [f32; 3] {
-    scale(a, -1.0)
+impl Vec3 {
+    fn new(x: f32, y: f32, z: f32) -> Vec3 {
+        Vec3 { x, y, z }
+    }
+
+    fn dot(self, other: Vec3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.y * other.z - self.z * other.y,
+                  self.z * other.x - self.x * other.z,
+                  self.x * other.y - self.y * other.x)
+    }
+
+    fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    fn normalize(self) -> Vec3 {
+        let inverse_length = 1.0 / self.length();
+        assert!(!inverse_length.is_infinite());
+        self * inverse_length
+    }
 }
 
-fn add(a: &[f32; 3], b: &[f32; 3]) -> [f32; 3] {
-    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+impl Add for Vec3 {
+    type Output = Vec3;
+    fn add(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
 }
 
-fn subtract(a: &[f32; 3], b: &[f32; 3]) -> [f32; 3] {
-    add(a, &negate(b))
+impl Sub for Vec3 {
+    type Output = Vec3;
+    fn sub(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
 }
 
-fn midpoint(a: &[f32; 3], b: &[f32; 3]) -> [f32; 3] {
-    scale(&add(a, b), 0.5)
+impl Neg for Vec3 {
+    type Output = Vec3;
+    fn neg(self) -> Vec3 { self * -1.0 }
 }
 
-fn length(a: &[f32; 3]) -> f32 {
-    f32::sqrt(a[0] * a[0] + a[1] * a[1] + a[2] * a[2])
+impl Mul<f32> for Vec3 {
+    type Output = Vec3;
+    fn mul(self, scalar: f32) -> Vec3 {
+        Vec3::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+
+unsafe impl glium::vertex::Attribute for Vec3 {
+    fn get_type() -> glium::vertex::AttributeType {
+        glium::vertex::AttributeType::F32F32F32
+    }
 }
 
-fn normalize(a: &[f32; 3]) -> [f32; 3] {
-    let inverse_length = 1.0 / length(a);
-    assert!(!inverse_length.is_infinite());
-    scale(a, inverse_length)
+/// Return the midpoint of `a` and `b`.
+fn midpoint(a: Vec3, b: Vec3) -> Vec3 {
+    (a + b) * 0.5
 }
 
 /// Return the point `angle` radians around the origin-centered ellipse whose
 /// major axis (center to zero-radians point) is `i` and whose minor axis
 /// (center to π/2) is `j`.
-fn mix_by_angle(i: &[f32; 3], j: &[f32; 3], angle: f32) -> [f32; 3] {
-    add(&scale(i, angle.cos()),
-        &scale(j, angle.sin()))
+fn mix_by_angle(i: Vec3, j: Vec3, angle: f32) -> Vec3 {
+    i * angle.cos() + j * angle.sin()
 }
 
 /// Return a unit vector in the XY plane that is rotated `angle` radians
 /// counter-clockwise from the X axis.
-fn unit_at_angle(angle: f32) -> [f32; 3] {
-    [ angle.cos(), angle.sin(), 0.0 ]
+fn unit_at_angle(angle: f32) -> Vec3 {
+    Vec3::new(angle.cos(), angle.sin(), 0.0)
+}
+
+/// Build a right-handed view matrix placing the camera at `eye`, looking
+/// towards `target`, with `up` as the approximate up direction.
+fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> [[f32; 4]; 4] {
+    let forward = (target - eye).normalize();
+    let side = forward.cross(up).normalize();
+    let up = side.cross(forward);
+    [
+        [side.x, up.x, -forward.x, 0.0],
+        [side.y, up.y, -forward.y, 0.0],
+        [side.z, up.z, -forward.z, 0.0],
+        [-side.dot(eye), -up.dot(eye), forward.dot(eye), 1.0],
+    ]
+}
+
+/// Build a perspective projection matrix for vertical field of view `fovy`
+/// (in radians), given the viewport's `aspect` ratio (width / height) and
+/// the depth range [`near`, `far`].
+fn perspective(fovy: f32, aspect: f32, near: f32, far: f32) -> [[f32; 4]; 4] {
+    let f = 1.0 / (fovy / 2.0).tan();
+    [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, (far + near) / (near - far), -1.0],
+        [0.0, 0.0, (2.0 * far * near) / (near - far), 0.0],
+    ]
+}
+
+fn matrix_multiply(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut out = [[0.0; 4]; 4];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[col][row] = (0..4).map(|k| a[k][row] * b[col][k]).sum();
+        }
+    }
+    out
+}
+
+/// An orbit camera: it always looks at `target`, from `distance` away,
+/// rotated `yaw` radians around the vertical axis and `pitch` radians above
+/// the horizontal plane through `target`. Driven by mouse drag (orbit) and
+/// the scroll wheel (zoom).
+struct Camera {
+    target: Vec3,
+    yaw: f32,
+    pitch: f32,
+    distance: f32
+}
+
+impl Camera {
+    fn new() -> Camera {
+        Camera {
+            target: Vec3::new(0.0, 0.0, -1.0),
+            yaw: 0.0,
+            pitch: 0.3,
+            distance: 4.0
+        }
+    }
+
+    /// Keep `pitch` just short of straight up/down, so the camera can't
+    /// flip over the pole and send the view spinning.
+    fn clamp_pitch(&mut self) {
+        let limit = FRAC_PI_2 - 0.01;
+        self.pitch = self.pitch.max(-limit).min(limit);
+    }
+
+    fn eye(&self) -> Vec3 {
+        let cos_pitch = self.pitch.cos();
+        let offset = Vec3::new(self.yaw.sin() * cos_pitch,
+                               self.pitch.sin(),
+                               self.yaw.cos() * cos_pitch);
+        self.target + offset * self.distance
+    }
+
+    /// Return the combined view-projection matrix for a viewport of the
+    /// given `aspect` ratio (width / height).
+    fn view_projection(&self, aspect: f32) -> [[f32; 4]; 4] {
+        let view = look_at(self.eye(), self.target, Vec3::new(0.0, 1.0, 0.0));
+        // The existing 24-bit depth buffer gives plenty of precision over
+        // this range for a scene this size.
+        let proj = perspective(FRAC_PI_2 * 0.5, aspect, 0.1, 100.0);
+        matrix_multiply(&proj, &view)
+    }
+}
+
+/// A piece of scene content that knows how to turn itself into triangle
+/// vertices, so the main loop can batch arbitrary drawables together instead
+/// of hand-inlining each one's geometry.
+trait PaintCommand {
+    /// Append this command's vertices to `out`. Front-facing geometry (if
+    /// any) must come first, so `front_vertex_count` can slice it back out.
+    fn paint(&self, out: &mut Vec<Vertex>);
+
+    /// How many of the vertices just appended by `paint` are front-facing,
+    /// for passes (like vane borders) that only draw silhouettes. Most
+    /// commands (a future `TextLabel`, `GridFloor`, ...) have none.
+    fn front_vertex_count(&self) -> usize { 0 }
+}
+
+/// Collects a frame's `PaintCommand`s and batches their vertices into the
+/// buffers the render passes draw from.
+struct Painter {
+    commands: Vec<Box<dyn PaintCommand>>
+}
+
+impl Painter {
+    fn new() -> Painter {
+        Painter { commands: Vec::new() }
+    }
+
+    /// Drop the previous frame's commands so the caller can repopulate it.
+    fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    fn push(&mut self, command: Box<dyn PaintCommand>) {
+        self.commands.push(command);
+    }
+
+    /// Batch every command's vertices together, for passes that draw the
+    /// whole scene. Also returns each command's front-facing vertex range
+    /// within the batch, so `front_faces` can slice them back out without
+    /// re-running `paint`.
+    fn paint_all(&self) -> (Vec<Vertex>, Vec<Range<usize>>) {
+        let mut vertices = Vec::new();
+        let mut front_ranges = Vec::new();
+        for command in &self.commands {
+            let start = vertices.len();
+            command.paint(&mut vertices);
+            front_ranges.push(start .. start + command.front_vertex_count());
+        }
+        (vertices, front_ranges)
+    }
+
+    /// Pick out just the front-facing vertices `paint_all` batched, for
+    /// passes that only draw silhouettes.
+    fn front_faces(vertices: &[Vertex], front_ranges: &[Range<usize>]) -> Vec<Vertex> {
+        let mut out = Vec::new();
+        for range in front_ranges {
+            out.extend_from_slice(&vertices[range.clone()]);
+        }
+        out
+    }
 }
 
 /// Properties identifying a windmill vane spinning about its axis of
 /// symmetry in 3-space, with a distinguished front face.
+#[derive(Clone, Copy)]
 struct Vane {
     /// Location of the vane's tip (the corner that lies on the axis of
     /// rotation).
-    tip: [f32; 3],
+    tip: Vec3,
 
     /// The midpoint of the vane's base (the side opposite the tip).
-    base_midpt: [f32; 3],
+    base_midpt: Vec3,
 
     /// Half the length of base - the distance from the base's midpoint to each
     /// adjacent corner.
@@ -74,11 +270,11 @@ struct Vane {
 
     /// Unit vector pointing from the midpoint of the base to the corner
     /// clockwise from the tip, in the unrotated state.
-    base_unit_i: [f32; 3],
+    base_unit_i: Vec3,
 
     /// Unit normal to the vane, pointing outwards from the front face,
     /// in the unrotated state.
-    base_unit_j: [f32; 3],
+    base_unit_j: Vec3,
 
     /// Rotation about the axis from the tip to base_midpt, in radians.
     spin: f32
@@ -88,13 +284,13 @@ enum Face { Front, Back }
 
 impl Vane {
     /// Return the positions of the tree corners of the given face of this vane.
-    fn corners(&self, face: Face) -> [[f32; 3]; 3] {
-        let unit_towards_corner = mix_by_angle(&self.base_unit_i,
-                                               &self.base_unit_j,
+    fn corners(&self, face: Face) -> [Vec3; 3] {
+        let unit_towards_corner = mix_by_angle(self.base_unit_i,
+                                               self.base_unit_j,
                                                self.spin);
-        let base_midpt_to_corner = scale(&unit_towards_corner, self.base_radius);
-        let corner1 = add(&self.base_midpt, &base_midpt_to_corner);
-        let corner2 = subtract(&self.base_midpt, &base_midpt_to_corner);
+        let base_midpt_to_corner = unit_towards_corner * self.base_radius;
+        let corner1 = self.base_midpt + base_midpt_to_corner;
+        let corner2 = self.base_midpt - base_midpt_to_corner;
         // Viewed from the front, each face's vertices must appear in clockwise order.
         match face {
             Face::Front => [self.tip, corner1, corner2],
@@ -102,12 +298,12 @@ impl Vane {
         }
     }
     /// Return a unit vector normal to the vane's given face.
-    fn normal(&self, face: Face) -> [f32; 3] {
-        let n = mix_by_angle(&self.base_unit_i, &self.base_unit_j,
+    fn normal(&self, face: Face) -> Vec3 {
+        let n = mix_by_angle(self.base_unit_i, self.base_unit_j,
                              self.spin + FRAC_PI_2);
         match face {
             Face::Front => n,
-            Face::Back => negate(&n)
+            Face::Back => -n
         }
     }
 
@@ -125,6 +321,23 @@ impl Vane {
     }
 }
 
+impl PaintCommand for Vane {
+    /// Append this vane's front face vertices, then its back face's.
+    fn paint(&self, out: &mut Vec<Vertex>) {
+        let normal = self.normal(Face::Front);
+        out.extend(self.corners(Face::Front).iter()
+                   .zip(self.texture_corners(Face::Front).iter())
+                   .map(|(&position, &texture)| Vertex { position, normal, texture }));
+
+        let normal = self.normal(Face::Back);
+        out.extend(self.corners(Face::Back).iter()
+                   .zip(self.texture_corners(Face::Back).iter())
+                   .map(|(&position, &texture)| Vertex { position, normal, texture }));
+    }
+
+    fn front_vertex_count(&self) -> usize { 3 }
+}
+
 static VANE_TEXTURE : &'static [u8] = include_bytes!("rainbow-vane-small.png");
 
 fn build_vane_texture(display: &Display) -> Result<Texture2d, Box<Error>>
@@ -137,13 +350,361 @@ fn build_vane_texture(display: &Display) -> Result<Texture2d, Box<Error>>
 
 #[derive(Clone, Copy, Debug)]
 struct Vertex {
-    position: [f32; 3],
-    normal: [f32; 3],
+    position: Vec3,
+    normal: Vec3,
     texture: [f32; 2]
 }
 
 implement_vertex!(Vertex, position, normal, texture);
 
+/// Half the width, in world units, of the rendered vane borders.
+const BORDER_HALF_WIDTH: f32 = 0.01;
+
+/// Beyond this ratio of miter length to `half_width`, a corner's join falls
+/// back to a bevel instead of shooting a spike out from the outline.
+const MITER_LIMIT: f32 = 4.0;
+
+/// Per-vertex data for the anti-aliased border stroke: a world-space
+/// position and its signed distance from the stroke's centerline, which
+/// `borders.frag` turns into coverage via `smoothstep`.
+#[derive(Clone, Copy)]
+struct BorderVertex {
+    position: Vec3,
+    distance: f32
+}
+
+implement_vertex!(BorderVertex, position, distance);
+
+/// Expand the (closed) triangle `points`, lying in the plane with the given
+/// `normal`, into stroke geometry `half_width` wide: offset each edge by its
+/// in-plane perpendicular to get a quad, then join adjacent quads at each
+/// corner with a miter, falling back to a bevel when the miter would spike
+/// out past `MITER_LIMIT`.
+fn stroke_triangle(points: &[Vec3; 3], normal: Vec3, half_width: f32,
+                    out: &mut Vec<BorderVertex>)
+{
+    let perpendiculars: Vec<Vec3> = (0..3).map(|i| {
+        let edge = points[(i + 1) % 3] - points[i];
+        normal.cross(edge.normalize()).normalize()
+    }).collect();
+
+    for i in 0..3 {
+        let a = points[i];
+        let b = points[(i + 1) % 3];
+        let offset = perpendiculars[i] * half_width;
+
+        let a_out = a + offset;
+        let a_in  = a - offset;
+        let b_out = b + offset;
+        let b_in  = b - offset;
+
+        out.extend_from_slice(&[
+            BorderVertex { position: a_out, distance:  half_width },
+            BorderVertex { position: b_out, distance:  half_width },
+            BorderVertex { position: b_in,  distance: -half_width },
+
+            BorderVertex { position: a_out, distance:  half_width },
+            BorderVertex { position: b_in,  distance: -half_width },
+            BorderVertex { position: a_in,  distance: -half_width },
+        ]);
+    }
+
+    for i in 0..3 {
+        let corner = points[i];
+        let prev_perp = perpendiculars[(i + 2) % 3];
+        let next_perp = perpendiculars[i];
+
+        let prev_outer = corner + prev_perp * half_width;
+        let next_outer = corner + next_perp * half_width;
+
+        let miter_sum = prev_perp + next_perp;
+        let miter_len = miter_sum.length();
+        let miter = if miter_len > 1e-6 {
+            let miter_dir = miter_sum * (1.0 / miter_len);
+            let cos_half_angle = miter_dir.dot(next_perp);
+            if cos_half_angle > 1.0 / MITER_LIMIT {
+                Some(corner + miter_dir * (half_width / cos_half_angle))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        match miter {
+            Some(miter_outer) => out.extend_from_slice(&[
+                BorderVertex { position: prev_outer,  distance: half_width },
+                BorderVertex { position: miter_outer, distance: half_width },
+                BorderVertex { position: corner,      distance: 0.0 },
+
+                BorderVertex { position: miter_outer, distance: half_width },
+                BorderVertex { position: next_outer,  distance: half_width },
+                BorderVertex { position: corner,      distance: 0.0 },
+            ]),
+            // Bevel: fill the wedge between the two segments' outer edges
+            // directly, without extending out to the (too-distant) miter.
+            None => out.extend_from_slice(&[
+                BorderVertex { position: prev_outer, distance: half_width },
+                BorderVertex { position: next_outer, distance: half_width },
+                BorderVertex { position: corner,     distance: 0.0 },
+            ]),
+        }
+    }
+}
+
+/// Stroke the outline of every front-face triangle in `faces` (as produced
+/// by `Painter::front_faces`).
+fn stroke_front_faces(faces: &[Vertex], half_width: f32) -> Vec<BorderVertex> {
+    let mut out = Vec::new();
+    for triangle in faces.chunks(3) {
+        let points = [triangle[0].position, triangle[1].position, triangle[2].position];
+        stroke_triangle(&points, triangle[0].normal, half_width, &mut out);
+    }
+    out
+}
+
+static TEXT_FONT: &'static [u8] = include_bytes!("dejavu-sans-mono.ttf");
+
+/// The atlas is a single square texture; glyphs are packed into it by a
+/// shelf packer, so this only needs to be big enough for the handful of
+/// sizes the demo actually uses.
+const GLYPH_ATLAS_SIZE: u32 = 512;
+
+/// A glyph's placement within the atlas, plus the metrics `draw_text` needs
+/// to position it relative to the pen.
+#[derive(Clone, Copy)]
+struct Glyph {
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+    width: u32,
+    height: u32,
+    xmin: i32,
+    ymin: i32,
+    advance_width: f32
+}
+
+#[derive(Clone, Copy)]
+struct TextVertex {
+    position: [f32; 2],
+    texture: [f32; 2]
+}
+
+implement_vertex!(TextVertex, position, texture);
+
+/// Draws text on top of a frame by rasterizing glyphs into a shared atlas
+/// texture on demand, analogous to how `build_vane_texture` loads the vane
+/// texture but built incrementally instead of all at once.
+struct TextRenderer {
+    font: fontdue::Font,
+    atlas: Texture2d,
+    /// Shelf packer state: the next free x within the current row, the y of
+    /// the current row, and the tallest glyph placed in that row so far.
+    cursor_x: u32,
+    cursor_y: u32,
+    row_height: u32,
+    glyphs: HashMap<(char, u32), Glyph>,
+    program: Program,
+    draw_parameters: DrawParameters<'static>
+}
+
+impl TextRenderer {
+    fn new(display: &Display) -> Result<TextRenderer, Box<Error>> {
+        let font = fontdue::Font::from_bytes(TEXT_FONT, fontdue::FontSettings::default())?;
+
+        let blank = vec![0u8; (GLYPH_ATLAS_SIZE * GLYPH_ATLAS_SIZE) as usize];
+        let raw_image = RawImage2d {
+            data: Cow::Owned(blank),
+            width: GLYPH_ATLAS_SIZE,
+            height: GLYPH_ATLAS_SIZE,
+            format: ClientFormat::U8
+        };
+        let atlas = Texture2d::with_format(display, raw_image,
+                                           UncompressedFloatFormat::U8,
+                                           MipmapsOption::NoMipmap)?;
+
+        let program = Program::from_source(display,
+                                           &include_str!("text.vert"),
+                                           &include_str!("text.frag"),
+                                           None)?;
+        let draw_parameters = DrawParameters {
+            blend: glium::Blend::alpha_blending(),
+            .. Default::default()
+        };
+
+        Ok(TextRenderer {
+            font,
+            atlas,
+            cursor_x: 0,
+            cursor_y: 0,
+            row_height: 0,
+            glyphs: HashMap::new(),
+            program,
+            draw_parameters
+        })
+    }
+
+    /// Rasterize `ch` at `px_size` if it isn't already cached, uploading its
+    /// coverage bitmap into the next free shelf of the atlas, and return its
+    /// placement and metrics.
+    fn glyph(&mut self, ch: char, px_size: u32) -> Result<Glyph, Box<Error>> {
+        let key = (ch, px_size);
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return Ok(*glyph);
+        }
+
+        let (metrics, bitmap) = self.font.rasterize(ch, px_size as f32);
+        let (width, height) = (metrics.width as u32, metrics.height as u32);
+
+        if self.cursor_x + width > GLYPH_ATLAS_SIZE {
+            self.cursor_x = 0;
+            self.cursor_y += self.row_height;
+            self.row_height = 0;
+        }
+        assert!(self.cursor_y + height <= GLYPH_ATLAS_SIZE, "glyph atlas is full");
+
+        if width > 0 && height > 0 {
+            let rect = Rect { left: self.cursor_x, bottom: self.cursor_y, width, height };
+            let raw_image = RawImage2d {
+                data: Cow::Owned(bitmap),
+                width,
+                height,
+                format: ClientFormat::U8
+            };
+            self.atlas.write(rect, raw_image);
+        }
+
+        let atlas_size = GLYPH_ATLAS_SIZE as f32;
+        let glyph = Glyph {
+            uv_min: [ self.cursor_x as f32 / atlas_size, self.cursor_y as f32 / atlas_size ],
+            uv_max: [ (self.cursor_x + width) as f32 / atlas_size,
+                      (self.cursor_y + height) as f32 / atlas_size ],
+            width,
+            height,
+            xmin: metrics.xmin,
+            ymin: metrics.ymin,
+            advance_width: metrics.advance_width
+        };
+
+        self.cursor_x += width;
+        self.row_height = self.row_height.max(height);
+        self.glyphs.insert(key, glyph);
+        Ok(glyph)
+    }
+
+    /// Draw `text` into `frame`, with its baseline starting at `position` in
+    /// window pixel coordinates (origin top-left), tinted by `color`.
+    fn draw_text(&mut self, display: &Display, frame: &mut glium::Frame,
+                  text: &str, position: (f32, f32), px_size: u32, color: [f32; 4])
+                  -> Result<(), Box<Error>>
+    {
+        let (screen_width, screen_height) = display.get_framebuffer_dimensions();
+        let (x, y) = position;
+        let mut pen_x = x;
+        let mut vertices = Vec::new();
+
+        for ch in text.chars() {
+            let glyph = self.glyph(ch, px_size)?;
+
+            let x0 = pen_x + glyph.xmin as f32;
+            let y0 = y - glyph.ymin as f32 - glyph.height as f32;
+            let x1 = x0 + glyph.width as f32;
+            let y1 = y0 + glyph.height as f32;
+
+            let [u0, v1] = glyph.uv_min;
+            let [u1, v0] = glyph.uv_max;
+
+            vertices.extend_from_slice(&[
+                TextVertex { position: [x0, y0], texture: [u0, v0] },
+                TextVertex { position: [x1, y0], texture: [u1, v0] },
+                TextVertex { position: [x1, y1], texture: [u1, v1] },
+                TextVertex { position: [x0, y0], texture: [u0, v0] },
+                TextVertex { position: [x1, y1], texture: [u1, v1] },
+                TextVertex { position: [x0, y1], texture: [u0, v1] },
+            ]);
+
+            pen_x += glyph.advance_width;
+        }
+
+        let vertex_buffer = VertexBuffer::new(display, &vertices)?;
+        frame.draw(&vertex_buffer, &glium::index::NoIndices(PrimitiveType::TrianglesList),
+                   &self.program,
+                   &uniform! {
+                       glyph_atlas: self.atlas.sampled()
+                           .wrap_function(SamplerWrapFunction::Clamp),
+                       screen_size: [screen_width as f32, screen_height as f32],
+                       text_color: color
+                   },
+                   &self.draw_parameters)?;
+        Ok(())
+    }
+}
+
+bitflags! {
+    /// Keyboard-toggled debug visualizations, threaded through the draw
+    /// code so a new visualization is just a new flag and a conditional
+    /// draw, not a recompile-and-edit.
+    struct DebugFlags: u32 {
+        const WIREFRAME = 0b001;
+        const NORMALS   = 0b010;
+        const PROFILER  = 0b100;
+    }
+}
+
+#[derive(Clone, Copy)]
+struct DebugLineVertex {
+    position: Vec3
+}
+
+implement_vertex!(DebugLineVertex, position);
+
+/// Build a short line segment from each vane's front-face centroid along
+/// its surface normal, for the `NORMALS` debug visualization.
+fn vane_normal_lines(vanes: &[Vane], length: f32) -> Vec<DebugLineVertex> {
+    let mut out = Vec::new();
+    for vane in vanes {
+        let corners = vane.corners(Face::Front);
+        let centroid = (corners[0] + corners[1] + corners[2]) * (1.0 / 3.0);
+        let tip = centroid + vane.normal(Face::Front) * length;
+        out.push(DebugLineVertex { position: centroid });
+        out.push(DebugLineVertex { position: tip });
+    }
+    out
+}
+
+/// Tracks frame times (in seconds) over a sliding window of the last
+/// `window` frames, for the `PROFILER` debug visualization.
+struct FrameProfiler {
+    samples: VecDeque<f32>,
+    window: usize
+}
+
+impl FrameProfiler {
+    fn new(window: usize) -> FrameProfiler {
+        FrameProfiler { samples: VecDeque::with_capacity(window), window }
+    }
+
+    fn record(&mut self, frame_seconds: f32) {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(frame_seconds);
+    }
+
+    /// Return (min, avg, max) frame time in milliseconds over the window.
+    fn min_avg_max_ms(&self) -> (f32, f32, f32) {
+        let mut min = f32::INFINITY;
+        let mut max: f32 = 0.0;
+        let mut sum = 0.0;
+        for &sample in &self.samples {
+            min = min.min(sample);
+            max = max.max(sample);
+            sum += sample;
+        }
+        let avg = if self.samples.is_empty() { 0.0 } else { sum / self.samples.len() as f32 };
+        (min * 1000.0, avg * 1000.0, max * 1000.0)
+    }
+}
+
 fn main() -> Result<(), Box<Error>> {
     let mut events_loop = EventsLoop::new();
     let window = WindowBuilder::new()
@@ -158,36 +719,59 @@ fn main() -> Result<(), Box<Error>> {
                              &include_str!("vane.vert"),
                              &include_str!("interior.frag"),
                              None)?;
-    let vane_interiors_draw_parameters =
+
+    /// Draw parameters for the vane interiors, rebuilt every frame so the
+    /// `WIREFRAME` debug flag can switch to line rendering without
+    /// recompiling.
+    fn vane_interiors_draw_parameters(wireframe: bool) -> DrawParameters<'static> {
         DrawParameters {
             depth: glium::Depth {
-                test: glium::DepthTest::IfMore,
+                test: glium::DepthTest::IfLess,
                 write: true,
                 .. Default::default()
             },
             backface_culling: BackfaceCullingMode::CullCounterClockwise,
+            polygon_mode: if wireframe { PolygonMode::Line } else { PolygonMode::Fill },
             .. Default::default()
-        };
+        }
+    }
 
     let vane_borders_program =
         Program::from_source(&display,
-                             &include_str!("vane.vert"),
+                             &include_str!("border.vert"),
                              &include_str!("borders.frag"),
                              None)?;
     let vane_borders_draw_parameters =
         DrawParameters {
             depth: glium::Depth {
-                test: glium::DepthTest::IfMore,
-                write: true,
+                test: glium::DepthTest::IfLess,
+                write: false,
+                .. Default::default()
+            },
+            blend: glium::Blend::alpha_blending(),
+            .. Default::default()
+        };
+
+    let debug_line_program =
+        Program::from_source(&display,
+                             &include_str!("debug_line.vert"),
+                             &include_str!("debug_line.frag"),
+                             None)?;
+    let debug_line_draw_parameters =
+        DrawParameters {
+            depth: glium::Depth {
+                test: glium::DepthTest::IfLess,
+                write: false,
                 .. Default::default()
             },
-            line_width: Some(2.0),
             .. Default::default()
         };
 
     let vane_texture = build_vane_texture(&display)?;
 
-    fn vane(pt: &[f32; 3], angle: f32) -> Vane {
+    let mut text_renderer = TextRenderer::new(&display)?;
+
+    fn vane(pt: Vec3, angle: f32) -> Vane {
         let inner_radius = 0.25;
         let outer_radius = 0.5;
 
@@ -195,95 +779,115 @@ fn main() -> Result<(), Box<Error>> {
         let unit1    = unit_at_angle(angle + PI * 7.0 / 6.0);
         let unit2    = unit_at_angle(angle + PI * 5.0 / 6.0);
 
-        let tip = scale(&unit_tip, inner_radius);
-        let corner1 = scale(&unit1, outer_radius);
-        let corner2 = scale(&unit2, outer_radius);
-        let base_midpt = midpoint(&corner1, &corner2);
-        let base_midpt_to_corner1 = subtract(&corner1, &base_midpt);
+        let tip = unit_tip * inner_radius;
+        let corner1 = unit1 * outer_radius;
+        let corner2 = unit2 * outer_radius;
+        let base_midpt = midpoint(corner1, corner2);
+        let base_midpt_to_corner1 = corner1 - base_midpt;
 
         Vane {
-            tip: add(pt, &tip),
-            base_midpt: add(pt, &base_midpt),
-            base_radius: length(&base_midpt_to_corner1),
-            base_unit_i: normalize(&base_midpt_to_corner1),
-            base_unit_j: [ 0.0, 0.0, 1.0 ],
+            tip: pt + tip,
+            base_midpt: pt + base_midpt,
+            base_radius: base_midpt_to_corner1.length(),
+            base_unit_i: base_midpt_to_corner1.normalize(),
+            base_unit_j: Vec3::new(0.0, 0.0, 1.0),
             spin: 0.0
         }
     }
 
     let mut vanes = [
-        vane(&[0.0, 0.0, 0.0], 0.0),
-        vane(&[0.0, 0.0, 0.0], PI * 2.0 / 3.0),
-        vane(&[0.0, 0.0, 0.0], PI * 4.0 / 3.0),
+        vane(Vec3::new(0.0, 0.0, 0.0), 0.0),
+        vane(Vec3::new(0.0, 0.0, 0.0), PI * 2.0 / 3.0),
+        vane(Vec3::new(0.0, 0.0, 0.0), PI * 4.0 / 3.0),
 
-        vane(&[1.0, 1.0, -2.5], 0.0),
-        vane(&[1.0, 1.0, -2.5], PI * 2.0 / 3.0),
-        vane(&[1.0, 1.0, -2.5], PI * 4.0 / 3.0),
+        vane(Vec3::new(1.0, 1.0, -2.5), 0.0),
+        vane(Vec3::new(1.0, 1.0, -2.5), PI * 2.0 / 3.0),
+        vane(Vec3::new(1.0, 1.0, -2.5), PI * 4.0 / 3.0),
     ];
 
+    let mut painter = Painter::new();
+    let mut camera = Camera::new();
+    let mut left_mouse_down = false;
+    let mut last_cursor_position: Option<(f64, f64)> = None;
+    let mut debug_flags = DebugFlags::empty();
+    let mut frame_profiler = FrameProfiler::new(120);
+
     let start_time = Instant::now();
+    let mut last_frame_start = start_time;
 
     let mut window_open = true;
     while window_open {
         let frame_time = Instant::now() - start_time;
+        let since_last_frame = Instant::now() - last_frame_start;
+        last_frame_start = Instant::now();
+        let since_last_frame_secs = since_last_frame.as_secs() as f32 +
+            since_last_frame.subsec_nanos() as f32 * 1e-9;
+        let fps = 1.0 / since_last_frame_secs;
+        frame_profiler.record(since_last_frame_secs);
 
         let seconds = frame_time.as_secs() as f32 +
             (frame_time.subsec_nanos() as f32 * 1e-9);
         let spin = seconds * 0.125 * 2.0 * PI;
 
         let mut frame = display.draw();
-        frame.clear_color_and_depth((0.8, 0.8, 0.8, 1.0), 0.0);
-
-        let mut vertices = Vec::new();
+        frame.clear_color_and_depth((0.8, 0.8, 0.8, 1.0), 1.0);
 
+        painter.clear();
         for (i, vane) in vanes.iter_mut().enumerate() {
             let spin = spin * (if i >= 3 { 0.5 } else { 1.0 });
             vane.spin = spin + i as f32;
+            painter.push(Box::new(*vane));
         }
 
-        // Put the front faces first; we'll re-use them as vertices for the
-        // border lines.
-        for vane in &vanes {
-            let normal = vane.normal(Face::Front);
-            vertices.extend(vane.corners(Face::Front).iter()
-                            .zip(vane.texture_corners(Face::Front).iter())
-                            .map(|(&position, &texture)|
-                                 Vertex { position, normal, texture }));
-        }
-
-        for vane in &vanes {
-            let normal = vane.normal(Face::Back);
-            vertices.extend(vane.corners(Face::Back).iter()
-                            .zip(vane.texture_corners(Face::Back).iter())
-                            .map(|(&position, &texture)|
-                                 Vertex { position, normal, texture }));
-        }
+        let (framebuffer_width, framebuffer_height) = display.get_framebuffer_dimensions();
+        let view_proj = camera.view_projection(framebuffer_width as f32 / framebuffer_height as f32);
 
+        let (vertices, front_ranges) = painter.paint_all();
         let vertex_buffer = VertexBuffer::new(&display, &vertices)?;
         frame.draw(&vertex_buffer, &glium::index::NoIndices(PrimitiveType::TrianglesList),
                    &vane_interiors_program,
                    &uniform! {
                        vane_texture: vane_texture
                            .sampled()
-                           .wrap_function(SamplerWrapFunction::Clamp)
+                           .wrap_function(SamplerWrapFunction::Clamp),
+                       view_proj: view_proj
                    },
-                   &vane_interiors_draw_parameters)?;
-
-        // Reuse just the front faces' vertices for the borders.
-        let border_vertex_buffer = VertexBuffer::new(&display, &vertices[0..18])?;
-        let indices: Vec<u16> = (0..6)
-            .flat_map(|n| {
-                let i = n * 3;
-                vec![ i, i+1,
-                      i+1, i+2,
-                      i+2, i ]
-            })
-            .collect();
-        let border_index_buffer = IndexBuffer::new(&display, PrimitiveType::LinesList,
-                                                   &indices)?;
-        frame.draw(&border_vertex_buffer, &border_index_buffer,
+                   &vane_interiors_draw_parameters(debug_flags.contains(DebugFlags::WIREFRAME)))?;
+
+        // A second pass over the same batch, keeping only each command's
+        // front-facing vertices, stroked into anti-aliased border geometry
+        // on the CPU.
+        let front_faces = Painter::front_faces(&vertices, &front_ranges);
+        let border_vertices = stroke_front_faces(&front_faces, BORDER_HALF_WIDTH);
+        let border_vertex_buffer = VertexBuffer::new(&display, &border_vertices)?;
+        frame.draw(&border_vertex_buffer, &glium::index::NoIndices(PrimitiveType::TrianglesList),
                    &vane_borders_program,
-                   &uniform! {}, &vane_borders_draw_parameters)?;
+                   &uniform! { view_proj: view_proj, half_width: BORDER_HALF_WIDTH },
+                   &vane_borders_draw_parameters)?;
+
+        if debug_flags.contains(DebugFlags::NORMALS) {
+            let normal_vertices = vane_normal_lines(&vanes, 0.3);
+            let normal_vertex_buffer = VertexBuffer::new(&display, &normal_vertices)?;
+            frame.draw(&normal_vertex_buffer, &glium::index::NoIndices(PrimitiveType::LinesList),
+                       &debug_line_program,
+                       &uniform! { view_proj: view_proj, line_color: [1.0f32, 0.0, 0.0, 1.0] },
+                       &debug_line_draw_parameters)?;
+        }
+
+        text_renderer.draw_text(&display, &mut frame, &format!("{:.0} fps", fps),
+                                (10.0, 20.0), 18, [0.0, 0.0, 0.0, 1.0])?;
+        for (i, _vane) in vanes.iter().enumerate() {
+            text_renderer.draw_text(&display, &mut frame, &format!("vane {}", i),
+                                    (10.0, 40.0 + i as f32 * 16.0), 14, [0.0, 0.0, 0.0, 1.0])?;
+        }
+        if debug_flags.contains(DebugFlags::PROFILER) {
+            let (min_ms, avg_ms, max_ms) = frame_profiler.min_avg_max_ms();
+            text_renderer.draw_text(&display, &mut frame,
+                                    &format!("frame ms min/avg/max: {:.1}/{:.1}/{:.1}",
+                                             min_ms, avg_ms, max_ms),
+                                    (10.0, 60.0 + vanes.len() as f32 * 16.0), 14,
+                                    [0.0, 0.0, 0.0, 1.0])?;
+        }
 
         frame.finish()?;
 
@@ -293,6 +897,54 @@ fn main() -> Result<(), Box<Error>> {
                 Event::WindowEvent { event: WindowEvent::Closed, .. } => {
                     window_open = false;
                 }
+
+                // Left-drag orbits the camera around its target.
+                Event::WindowEvent {
+                    event: WindowEvent::MouseInput { state, button: MouseButton::Left, .. }, ..
+                } => {
+                    left_mouse_down = state == ElementState::Pressed;
+                    if !left_mouse_down {
+                        last_cursor_position = None;
+                    }
+                }
+                Event::WindowEvent { event: WindowEvent::CursorMoved { position, .. }, .. } => {
+                    if left_mouse_down {
+                        if let Some((last_x, last_y)) = last_cursor_position {
+                            camera.yaw -= (position.0 - last_x) as f32 * 0.005;
+                            camera.pitch += (position.1 - last_y) as f32 * 0.005;
+                            camera.clamp_pitch();
+                        }
+                    }
+                    last_cursor_position = Some(position);
+                }
+
+                // The scroll wheel zooms by moving the camera closer to or
+                // further from its target.
+                Event::WindowEvent { event: WindowEvent::MouseWheel { delta, .. }, .. } => {
+                    let scroll = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => y,
+                        MouseScrollDelta::PixelDelta(_, y) => y as f32 * 0.01
+                    };
+                    camera.distance = (camera.distance - scroll * 0.25).max(0.5);
+                }
+
+                // Keys toggle the debug visualizations in `DebugFlags`.
+                Event::WindowEvent {
+                    event: WindowEvent::KeyboardInput {
+                        input: KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(key), ..
+                        }, ..
+                    }, ..
+                } => {
+                    match key {
+                        VirtualKeyCode::W => debug_flags.toggle(DebugFlags::WIREFRAME),
+                        VirtualKeyCode::N => debug_flags.toggle(DebugFlags::NORMALS),
+                        VirtualKeyCode::P => debug_flags.toggle(DebugFlags::PROFILER),
+                        _ => (),
+                    }
+                }
+
                 _ => (),
             }
         });